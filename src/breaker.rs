@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerHealth {
+    /// Calls go through.
+    Healthy,
+    /// Too many consecutive failures; calls are skipped until the cooldown
+    /// ends.
+    Open,
+    /// The cooldown has ended and a single probe is allowed through per
+    /// cooldown.
+    Probing,
+}
+
+/// Stops calling a peer after repeated failures, then probes it with a
+/// single call once per cooldown until it answers again.
+///
+/// Time is passed in by the caller, usually from a `Clock`.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: usize,
+    cooldown: Duration,
+    failures: usize,
+    opened_at: Option<Instant>,
+    /// When the outstanding probe was let through.
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Opens after `threshold` consecutive failures and stays open for
+    /// `cooldown` before probing. A `threshold` of 0 is treated as 1: the
+    /// first failure opens the breaker.
+    pub fn new(threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            failures: 0,
+            opened_at: None,
+            probe_started: None,
+        }
+    }
+
+    pub fn health(&self, now: Instant) -> PeerHealth {
+        match self.opened_at {
+            None => PeerHealth::Healthy,
+            Some(opened_at) if now.saturating_duration_since(opened_at) < self.cooldown => {
+                PeerHealth::Open
+            }
+            Some(_) => PeerHealth::Probing,
+        }
+    }
+
+    /// Whether to make a call now. While probing, one caller is let
+    /// through per cooldown, so a probe whose result is never recorded
+    /// doesn't lock the peer out.
+    pub fn allow(&mut self, now: Instant) -> bool {
+        match self.health(now) {
+            PeerHealth::Healthy => true,
+            PeerHealth::Open => false,
+            PeerHealth::Probing => match self.probe_started {
+                Some(started) if now.saturating_duration_since(started) < self.cooldown => false,
+                _ => {
+                    self.probe_started = Some(now);
+                    true
+                }
+            },
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+        self.opened_at = None;
+        self.probe_started = None;
+    }
+
+    /// A failed probe restarts the cooldown.
+    pub fn record_failure(&mut self, now: Instant) {
+        self.failures += 1;
+        self.probe_started = None;
+        if self.failures >= self.threshold {
+            self.opened_at = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Clock, SimClock};
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let clock = SimClock::new();
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(1));
+        for _ in 0..2 {
+            assert!(breaker.allow(clock.now()));
+            breaker.record_failure(clock.now());
+        }
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Healthy);
+        breaker.record_failure(clock.now());
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Open);
+        assert!(!breaker.allow(clock.now()));
+    }
+
+    #[test]
+    fn test_zero_threshold_opens_on_first_failure() {
+        let clock = SimClock::new();
+        let mut breaker = CircuitBreaker::new(0, Duration::from_secs(1));
+        assert!(breaker.allow(clock.now()));
+        breaker.record_failure(clock.now());
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Open);
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let clock = SimClock::new();
+        let mut breaker = CircuitBreaker::new(2, Duration::from_secs(1));
+        breaker.record_failure(clock.now());
+        breaker.record_success();
+        breaker.record_failure(clock.now());
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Healthy);
+    }
+
+    #[test]
+    fn test_probe_after_cooldown() {
+        let clock = SimClock::new();
+        let cooldown = Duration::from_secs(1);
+        let mut breaker = CircuitBreaker::new(1, cooldown);
+        breaker.record_failure(clock.now());
+
+        let skipped = (0..10)
+            .filter(|_| {
+                clock.advance(Duration::from_millis(50));
+                !breaker.allow(clock.now())
+            })
+            .count();
+        assert_eq!(skipped, 10);
+
+        clock.advance(cooldown);
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Probing);
+        assert!(breaker.allow(clock.now()));
+        assert!(!breaker.allow(clock.now()));
+
+        breaker.record_failure(clock.now());
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Open);
+
+        clock.advance(cooldown);
+        assert!(breaker.allow(clock.now()));
+        breaker.record_success();
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Healthy);
+        assert!(breaker.allow(clock.now()));
+        assert!(breaker.allow(clock.now()));
+    }
+
+    #[test]
+    fn test_lost_probe_is_retried() {
+        let clock = SimClock::new();
+        let cooldown = Duration::from_secs(1);
+        let mut breaker = CircuitBreaker::new(1, cooldown);
+        breaker.record_failure(clock.now());
+
+        clock.advance(cooldown);
+        assert!(breaker.allow(clock.now()));
+        // The probe's result is never recorded.
+        clock.advance(cooldown / 2);
+        assert!(!breaker.allow(clock.now()));
+        assert_eq!(breaker.health(clock.now()), PeerHealth::Probing);
+
+        clock.advance(cooldown / 2);
+        assert!(breaker.allow(clock.now()));
+        assert!(!breaker.allow(clock.now()));
+
+        clock.advance(Duration::from_secs(3600));
+        assert!(breaker.allow(clock.now()));
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Source of time for timers, so tests can control it.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn sleep(&self, duration: Duration);
+}
+
+/// Wall-clock time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Virtual time that only moves when advanced.
+///
+/// Clones share the same time. `sleep` returns immediately after advancing
+/// the clock by the requested duration, as if the sleeper were the only
+/// thing running.
+#[derive(Debug, Clone)]
+pub struct SimClock {
+    start: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Arc::new(Mutex::new(Duration::from_secs(0))),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// Virtual time passed since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sim_clock_only_moves_when_advanced() {
+        let clock = SimClock::new();
+        let before = clock.now();
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(clock.now(), before);
+
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(clock.now() - before, Duration::from_millis(10));
+        clock.sleep(Duration::from_millis(5));
+        assert_eq!(clock.elapsed(), Duration::from_millis(15));
+    }
+
+    #[test]
+    fn test_sim_clock_clones_share_time() {
+        let clock = SimClock::new();
+        let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(shared.now(), clock.now());
+    }
+
+    #[test]
+    fn test_system_clock_moves() {
+        let clock = SystemClock;
+        let before = clock.now();
+        clock.sleep(Duration::from_millis(1));
+        assert!(clock.now() > before);
+    }
+}
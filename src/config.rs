@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use crate::{Error, Result};
+
+/// Tunable parameters of a node. `Config::default()` suits a LAN cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Lower bound of the randomized election timeout.
+    pub election_timeout_min: Duration,
+    /// Upper bound (exclusive) of the randomized election timeout.
+    pub election_timeout_max: Duration,
+    /// How often a leader sends heartbeats; at most half of
+    /// `election_timeout_min`, so a single late heartbeat doesn't make
+    /// followers time out.
+    pub heartbeat_interval: Duration,
+    /// Deadline for a single RPC to a peer.
+    pub rpc_timeout: Duration,
+    /// Most entries a leader puts into one AppendEntries message.
+    pub max_entries_per_msg: usize,
+    /// Most payload bytes a leader puts into one AppendEntries message. A
+    /// single entry larger than this is still sent on its own.
+    pub max_size_per_msg: usize,
+    /// Number of applied entries after which the log is compacted into a
+    /// snapshot.
+    pub snapshot_threshold: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            election_timeout_min: Duration::from_millis(150),
+            election_timeout_max: Duration::from_millis(300),
+            heartbeat_interval: Duration::from_millis(75),
+            rpc_timeout: Duration::from_millis(50),
+            max_entries_per_msg: 64,
+            max_size_per_msg: 1024 * 1024,
+            snapshot_threshold: 10_000,
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        if self.election_timeout_min >= self.election_timeout_max {
+            return Err(Error::InvalidConfig(
+                "election_timeout_min must be less than election_timeout_max",
+            ));
+        }
+        if self.heartbeat_interval.as_nanos() == 0 {
+            return Err(Error::InvalidConfig("heartbeat_interval must be positive"));
+        }
+        if self.heartbeat_interval > self.election_timeout_min / 2 {
+            return Err(Error::InvalidConfig(
+                "heartbeat_interval must be at most half of election_timeout_min",
+            ));
+        }
+        if self.rpc_timeout.as_nanos() == 0 {
+            return Err(Error::InvalidConfig("rpc_timeout must be positive"));
+        }
+        if self.rpc_timeout > self.election_timeout_min {
+            return Err(Error::InvalidConfig(
+                "rpc_timeout must not exceed election_timeout_min",
+            ));
+        }
+        if self.max_entries_per_msg == 0 {
+            return Err(Error::InvalidConfig("max_entries_per_msg must be positive"));
+        }
+        if self.max_size_per_msg == 0 {
+            return Err(Error::InvalidConfig("max_size_per_msg must be positive"));
+        }
+        if self.snapshot_threshold == 0 {
+            return Err(Error::InvalidConfig("snapshot_threshold must be positive"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_valid() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_timing() {
+        let ms = Duration::from_millis;
+        for (min, max, heartbeat, valid) in [
+            (ms(50), ms(100), ms(10), true),
+            (ms(100), ms(100), ms(10), false),
+            (ms(200), ms(100), ms(10), false),
+            (ms(100), ms(200), ms(50), true),
+            (ms(100), ms(200), ms(51), false),
+            (ms(100), ms(200), ms(100), false),
+            (ms(100), ms(200), ms(150), false),
+            (ms(100), ms(200), ms(0), false),
+            (ms(100), ms(200), Duration::MAX, false),
+        ]
+        .iter()
+        {
+            let config = Config {
+                election_timeout_min: *min,
+                election_timeout_max: *max,
+                heartbeat_interval: *heartbeat,
+                ..Config::default()
+            };
+            assert_eq!(config.validate().is_ok(), *valid, "{:?}", config);
+        }
+    }
+
+    #[test]
+    fn test_validate_limits() {
+        let ms = Duration::from_millis;
+        for (config, reason) in [
+            (
+                Config {
+                    rpc_timeout: ms(0),
+                    ..Config::default()
+                },
+                "rpc_timeout must be positive",
+            ),
+            (
+                Config {
+                    rpc_timeout: ms(151),
+                    ..Config::default()
+                },
+                "rpc_timeout must not exceed election_timeout_min",
+            ),
+            (
+                Config {
+                    max_entries_per_msg: 0,
+                    ..Config::default()
+                },
+                "max_entries_per_msg must be positive",
+            ),
+            (
+                Config {
+                    max_size_per_msg: 0,
+                    ..Config::default()
+                },
+                "max_size_per_msg must be positive",
+            ),
+            (
+                Config {
+                    snapshot_threshold: 0,
+                    ..Config::default()
+                },
+                "snapshot_threshold must be positive",
+            ),
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                config.validate(),
+                Err(Error::InvalidConfig(v)) if v == *reason
+            ));
+        }
+    }
+}
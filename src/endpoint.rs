@@ -0,0 +1,310 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Network address of a cluster member, written as `host:port`.
+///
+/// IPv6 hosts use the bracket syntax, e.g. `[::1]:8080`. Hosts are kept in
+/// normalized form (lowercase names without a trailing dot, canonical IP
+/// addresses), so equality and hashing don't depend on how a peer was
+/// spelled.
+///
+/// ```
+/// use raft::Endpoint;
+///
+/// let endpoint: Endpoint = "[::1]:8080".parse().unwrap();
+/// assert_eq!(endpoint.host(), "::1");
+/// assert_eq!(endpoint.port(), 8080);
+/// assert_eq!(endpoint.to_string(), "[::1]:8080");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Endpoint {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseEndpointError {
+    Empty,
+    MissingPort,
+    InvalidHost(String),
+    InvalidPort(String),
+}
+
+impl fmt::Display for ParseEndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseEndpointError::Empty => write!(f, "endpoint is empty"),
+            ParseEndpointError::MissingPort => write!(f, "endpoint has no port"),
+            ParseEndpointError::InvalidHost(host) => write!(f, "invalid host `{}`", host),
+            ParseEndpointError::InvalidPort(port) => write!(f, "invalid port `{}`", port),
+        }
+    }
+}
+
+impl std::error::Error for ParseEndpointError {}
+
+impl Endpoint {
+    /// Builds an endpoint from an unbracketed host, which may be a name or
+    /// an IPv4/IPv6 address.
+    pub fn new<S: AsRef<str>>(host: S, port: u16) -> Result<Self, ParseEndpointError> {
+        Ok(Self {
+            host: normalize_host(host.as_ref())?,
+            port,
+        })
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+/// Validates `host` and returns its normalized form: canonical IP
+/// addresses, and lowercase names without the trailing root dot.
+fn normalize_host(host: &str) -> Result<String, ParseEndpointError> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(ip.to_string());
+    }
+    let name = host.strip_suffix('.').unwrap_or(host);
+    if !is_valid_hostname(name) {
+        return Err(ParseEndpointError::InvalidHost(host.to_owned()));
+    }
+    Ok(name.to_ascii_lowercase())
+}
+
+fn parse_port(port: &str) -> Result<u16, ParseEndpointError> {
+    if port.is_empty() {
+        return Err(ParseEndpointError::MissingPort);
+    }
+    if !port.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseEndpointError::InvalidPort(port.to_owned()));
+    }
+    port.parse()
+        .map_err(|_| ParseEndpointError::InvalidPort(port.to_owned()))
+}
+
+/// RFC 1123 names: at most 253 characters of dot-separated labels, each 1
+/// to 63 letters, digits or `-` that neither starts nor ends with `-`.
+///
+/// A name whose last label is all digits is rejected, since it can only be
+/// a malformed IPv4 address (`01.2.3.4`, `999.999.999.999`, `12345`) that
+/// would otherwise become a second endpoint for the same peer.
+fn is_valid_hostname(host: &str) -> bool {
+    let labels_ok = host.split('.').all(|label| {
+        (1..=63).contains(&label.len())
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    let numeric_tld = host
+        .rsplit('.')
+        .next()
+        .is_some_and(|tld| tld.bytes().all(|b| b.is_ascii_digit()));
+    host.len() <= 253 && labels_ok && !numeric_tld
+}
+
+impl FromStr for Endpoint {
+    type Err = ParseEndpointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseEndpointError::Empty);
+        }
+
+        if let Some(rest) = s.strip_prefix('[') {
+            let end = rest
+                .find(']')
+                .ok_or_else(|| ParseEndpointError::InvalidHost(s.to_owned()))?;
+            let host = &rest[..end];
+            if host.parse::<Ipv6Addr>().is_err() {
+                return Err(ParseEndpointError::InvalidHost(host.to_owned()));
+            }
+            let port = rest[end + 1..]
+                .strip_prefix(':')
+                .ok_or(ParseEndpointError::MissingPort)?;
+            return Endpoint::new(host, parse_port(port)?);
+        }
+
+        let (host, port) = s.rsplit_once(':').ok_or(ParseEndpointError::MissingPort)?;
+        // An IPv6 host must be bracketed to tell it apart from the port.
+        if host.contains(':') {
+            return Err(ParseEndpointError::InvalidHost(host.to_owned()));
+        }
+        let host = normalize_host(host)?;
+        Ok(Endpoint {
+            host,
+            port: parse_port(port)?,
+        })
+    }
+}
+
+impl TryFrom<&str> for Endpoint {
+    type Error = ParseEndpointError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = ParseEndpointError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for (input, host, port) in [
+            ("127.0.0.1:8080", "127.0.0.1", 8080),
+            ("localhost:1", "localhost", 1),
+            ("raft-0.svc.local:65535", "raft-0.svc.local", 65535),
+            ("[::1]:8080", "::1", 8080),
+            ("[fe80::1:2]:80", "fe80::1:2", 80),
+        ]
+        .iter()
+        {
+            let endpoint: Endpoint = input.parse().unwrap();
+            assert_eq!(endpoint, Endpoint::new(*host, *port).unwrap());
+            assert_eq!(endpoint.to_string(), *input);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        use ParseEndpointError::*;
+
+        for (input, err) in [
+            ("", Empty),
+            ("   ", Empty),
+            ("localhost", MissingPort),
+            ("localhost:", MissingPort),
+            (":8080", InvalidHost("".to_owned())),
+            ("local host:8080", InvalidHost("local host".to_owned())),
+            ("::1:8080", InvalidHost("::1".to_owned())),
+            ("[::1]", MissingPort),
+            ("[::1", InvalidHost("[::1".to_owned())),
+            ("[nope]:8080", InvalidHost("nope".to_owned())),
+            ("...:80", InvalidHost("...".to_owned())),
+            (".:80", InvalidHost(".".to_owned())),
+            ("a..:80", InvalidHost("a..".to_owned())),
+            (".a:80", InvalidHost(".a".to_owned())),
+            (
+                "999.999.999.999:80",
+                InvalidHost("999.999.999.999".to_owned()),
+            ),
+            ("01.2.3.4:80", InvalidHost("01.2.3.4".to_owned())),
+            ("1.2.3:80", InvalidHost("1.2.3".to_owned())),
+            ("12345:80", InvalidHost("12345".to_owned())),
+            ("-node:80", InvalidHost("-node".to_owned())),
+            ("node-.local:80", InvalidHost("node-.local".to_owned())),
+            ("node_1:80", InvalidHost("node_1".to_owned())),
+            ("localhost:http", InvalidPort("http".to_owned())),
+            ("localhost:65536", InvalidPort("65536".to_owned())),
+            ("localhost:+80", InvalidPort("+80".to_owned())),
+            ("localhost: 80", InvalidPort(" 80".to_owned())),
+        ]
+        .iter()
+        {
+            assert_eq!(input.parse::<Endpoint>(), Err(err.clone()), "{}", input);
+        }
+
+        let label = "a".repeat(64);
+        assert_eq!(Endpoint::new(&label, 80), Err(InvalidHost(label.clone())));
+        assert!(Endpoint::new(&label[..63], 80).is_ok());
+        let long = [&label[..63]; 4].join(".");
+        assert_eq!(long.len(), 255);
+        assert_eq!(Endpoint::new(&long, 80), Err(InvalidHost(long.clone())));
+        assert!(Endpoint::new(&long[..253], 80).is_ok());
+    }
+
+    #[test]
+    fn test_normalized_equality() {
+        for (a, b) in [
+            ("LocalHost:8080", "localhost:8080"),
+            ("raft-0.svc.local.:80", "raft-0.svc.local:80"),
+            ("[0:0:0:0:0:0:0:1]:80", "[::1]:80"),
+            ("[FE80::1]:80", "[fe80::1]:80"),
+        ]
+        .iter()
+        {
+            let a: Endpoint = a.parse().unwrap();
+            let b: Endpoint = b.parse().unwrap();
+            assert_eq!(a, b);
+            assert_eq!(a.to_string(), b.to_string());
+        }
+        assert_ne!(
+            "localhost:8080".parse::<Endpoint>(),
+            "localhost:8081".parse::<Endpoint>()
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for input in [
+            "Raft-0.Local.:9000",
+            "[::FFFF:1.2.3.4]:1",
+            "10.0.0.1:2",
+            "a.:3",
+        ]
+        .iter()
+        {
+            let endpoint: Endpoint = input.parse().unwrap();
+            assert_eq!(endpoint.to_string().parse::<Endpoint>(), Ok(endpoint));
+        }
+    }
+
+    #[test]
+    fn test_new_rejects() {
+        use ParseEndpointError::*;
+
+        for host in ["", "[::1]", "a b", "...", "a:b", "host/path"].iter() {
+            assert_eq!(
+                Endpoint::new(*host, 80),
+                Err(InvalidHost((*host).to_owned())),
+                "{}",
+                host
+            );
+        }
+    }
+
+    #[test]
+    fn test_new_accepts_bare_ipv6() {
+        let endpoint = Endpoint::new("::1", 80).unwrap();
+        assert_eq!(endpoint.to_string(), "[::1]:80");
+        assert_eq!(endpoint.to_string().parse(), Ok(endpoint));
+    }
+
+    #[test]
+    fn test_try_from_string() {
+        assert_eq!(
+            Endpoint::try_from("127.0.0.1:8080".to_owned()),
+            Endpoint::new("127.0.0.1", 8080)
+        );
+        assert_eq!(
+            Endpoint::try_from(String::new()),
+            Err(ParseEndpointError::Empty)
+        );
+    }
+}
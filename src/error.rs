@@ -0,0 +1,153 @@
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use crate::{Endpoint, ParseEndpointError, SequenceID};
+
+#[derive(Debug)]
+pub enum Error {
+    /// A call to a peer failed.
+    Rpc {
+        endpoint: Endpoint,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// `op` did not complete within `after`.
+    Timeout {
+        op: &'static str,
+        after: Duration,
+    },
+    Storage(io::Error),
+    /// The entry a leader expected to find is missing or from another term.
+    LogConflict {
+        expected: SequenceID,
+        found: Option<SequenceID>,
+    },
+    /// Entries before `first_index` have been compacted into a snapshot.
+    Compacted {
+        first_index: usize,
+    },
+    /// This node is not the leader; `hint` is the leader it knows of.
+    NotLeader {
+        hint: Option<Endpoint>,
+    },
+    Shutdown,
+    InvalidEndpoint(ParseEndpointError),
+    MemberExists(Endpoint),
+    MemberNotFound(Endpoint),
+    NotLearner(Endpoint),
+    EmptyMembership,
+    ChangePending,
+    TooManyVoters(usize),
+    TooManyMembers(usize),
+    InvalidConfig(&'static str),
+    CorruptSnapshot(&'static str),
+    UnsupportedSnapshotVersion(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Rpc { endpoint, source } => write!(f, "rpc to {} failed: {}", endpoint, source),
+            Error::Timeout { op, after } => write!(f, "{} timed out after {:?}", op, after),
+            Error::Storage(err) => write!(f, "storage error: {}", err),
+            Error::LogConflict { expected, found } => write!(
+                f,
+                "log conflict: expected {:?}, found {:?}",
+                expected, found
+            ),
+            Error::Compacted { first_index } => {
+                write!(f, "entries before {} have been compacted", first_index)
+            }
+            Error::NotLeader { hint: Some(leader) } => {
+                write!(f, "not the leader, try {}", leader)
+            }
+            Error::NotLeader { hint: None } => write!(f, "not the leader"),
+            Error::Shutdown => write!(f, "node is shut down"),
+            Error::InvalidEndpoint(err) => write!(f, "invalid endpoint: {}", err),
+            Error::MemberExists(endpoint) => write!(f, "{} is already a member", endpoint),
+            Error::MemberNotFound(endpoint) => write!(f, "{} is not a member", endpoint),
+            Error::NotLearner(endpoint) => write!(f, "{} is not a learner", endpoint),
+            Error::EmptyMembership => write!(f, "membership must keep at least one voter"),
+            Error::ChangePending => write!(f, "a membership change is still uncommitted"),
+            Error::TooManyVoters(limit) => write!(f, "more than {} voters", limit),
+            Error::TooManyMembers(limit) => write!(f, "more than {} members", limit),
+            Error::InvalidConfig(reason) => write!(f, "invalid config: {}", reason),
+            Error::CorruptSnapshot(reason) => write!(f, "corrupt snapshot: {}", reason),
+            Error::UnsupportedSnapshotVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Rpc { source, .. } => Some(source.as_ref()),
+            Error::Storage(err) => Some(err),
+            Error::InvalidEndpoint(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Storage(err)
+    }
+}
+
+impl From<ParseEndpointError> for Error {
+    fn from(err: ParseEndpointError) -> Self {
+        Error::InvalidEndpoint(err)
+    }
+}
+
+impl From<(Endpoint, String)> for Error {
+    fn from((endpoint, reason): (Endpoint, String)) -> Self {
+        Error::Rpc {
+            endpoint,
+            source: reason.into(),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn test_source() {
+        let err = Error::from(io::Error::other("disk full"));
+        assert_eq!(err.source().unwrap().to_string(), "disk full");
+
+        let endpoint = Endpoint::new("127.0.0.1", 8080).unwrap();
+        let err = Error::from((endpoint, "connection refused".to_owned()));
+        assert_eq!(
+            err.to_string(),
+            "rpc to 127.0.0.1:8080 failed: connection refused"
+        );
+        assert_eq!(err.source().unwrap().to_string(), "connection refused");
+
+        let err = Error::from(ParseEndpointError::Empty);
+        assert!(err.source().is_some());
+        assert!(Error::Shutdown.source().is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        let err = Error::NotLeader {
+            hint: Some(Endpoint::new("::1", 8080).unwrap()),
+        };
+        assert_eq!(err.to_string(), "not the leader, try [::1]:8080");
+        let err = Error::Timeout {
+            op: "append",
+            after: Duration::from_millis(50),
+        };
+        assert_eq!(err.to_string(), "append timed out after 50ms");
+    }
+}
@@ -1,3 +1,27 @@
+mod breaker;
+mod clock;
+mod config;
+mod endpoint;
+mod error;
+mod logger;
+mod membership;
+mod metrics;
+mod session;
+mod snapshot;
+mod storage;
+
+pub use breaker::{CircuitBreaker, PeerHealth};
+pub use clock::{Clock, SimClock, SystemClock};
+pub use config::Config;
+pub use endpoint::{Endpoint, ParseEndpointError};
+pub use error::{Error, Result};
+pub use logger::{Entry, Logger, RequestID, SequenceID, NO_LIMIT};
+pub use membership::{majority, MemberKind, Membership, MembershipLimits};
+pub use metrics::{AtomicMetrics, Metrics, MetricsSnapshot, NoopMetrics};
+pub use session::Sessions;
+pub use snapshot::{Snapshot, SnapshotComponent, SNAPSHOT_VERSION};
+pub use storage::{load_hard_state, save_hard_state, HardState};
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,534 @@
+use crate::{Error, Result};
+
+/// `max_bytes` for `Logger::entries` that doesn't limit the batch size.
+pub const NO_LIMIT: usize = usize::MAX;
+
+/// Position of an entry in the replicated log.
+///
+/// Ordering compares `term` first and then `index`, which is the
+/// "at least as up-to-date" comparison used when granting votes.
+///
+/// ```
+/// use raft::SequenceID;
+///
+/// let id = SequenceID::new(2, 10);
+/// assert_eq!(id.term(), 2);
+/// assert_eq!(id.index(), 10);
+/// assert!(SequenceID::new(1, 20) < id);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SequenceID {
+    term: usize,
+    index: usize,
+}
+
+impl SequenceID {
+    pub fn new(term: usize, index: usize) -> Self {
+        Self { term, index }
+    }
+
+    pub fn term(&self) -> usize {
+        self.term
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// Identifies a client proposal so retries of it can be detected.
+///
+/// Each client numbers its requests with increasing `sequence_no`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestID {
+    pub client_id: u64,
+    pub sequence_no: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    seq_id: SequenceID,
+    data: Vec<u8>,
+    request: Option<RequestID>,
+}
+
+impl Entry {
+    pub fn new(seq_id: SequenceID, data: Vec<u8>) -> Self {
+        Self {
+            seq_id,
+            data,
+            request: None,
+        }
+    }
+
+    pub fn with_request(mut self, request: RequestID) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    pub fn request(&self) -> Option<RequestID> {
+        self.request
+    }
+
+    pub fn seq_id(&self) -> SequenceID {
+        self.seq_id
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// The replicated log.
+///
+/// Indices start at 1; index 0 is the position before the first entry.
+/// Entries up to the snapshot point have been compacted away, and only the
+/// `SequenceID` of the last compacted entry is kept.
+#[derive(Debug, Default)]
+pub struct Logger {
+    snapshot: SequenceID,
+    entries: Vec<Entry>,
+    committed: usize,
+    applied: usize,
+}
+
+impl Logger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `data` at the end of the log as an entry of `term`.
+    pub fn append(&mut self, term: usize, data: Vec<u8>) -> SequenceID {
+        let seq_id = SequenceID::new(term, self.last_index() + 1);
+        self.entries.push(Entry::new(seq_id, data));
+        seq_id
+    }
+
+    /// Index of the first entry still held, which is one past the snapshot.
+    /// Greater than `last_index()` when the log holds no entries.
+    pub fn first_index(&self) -> usize {
+        self.snapshot.index + 1
+    }
+
+    pub fn last_index(&self) -> usize {
+        self.snapshot.index + self.entries.len()
+    }
+
+    pub fn last_seq_id(&self) -> SequenceID {
+        self.entries
+            .last()
+            .map(Entry::seq_id)
+            .unwrap_or(self.snapshot)
+    }
+
+    /// Entries in `[from, to)`, with `to` clamped to the end of the log.
+    ///
+    /// The batch stops before the entry that would take its payload past
+    /// `max_bytes`, but always includes the first entry so a single large
+    /// entry still makes progress. Pass `NO_LIMIT` for no size cap.
+    ///
+    /// Fails with `Error::Compacted` when `from` precedes `first_index()`,
+    /// meaning the caller needs the snapshot instead.
+    pub fn entries(&self, from: usize, to: usize, max_bytes: usize) -> Result<Vec<Entry>> {
+        if from < self.first_index() {
+            return Err(Error::Compacted {
+                first_index: self.first_index(),
+            });
+        }
+        let to = to.min(self.last_index() + 1);
+        if from >= to {
+            return Ok(Vec::new());
+        }
+        let offset = self.first_index();
+        let mut size = 0;
+        let batch = self.entries[from - offset..to - offset]
+            .iter()
+            .enumerate()
+            .take_while(|(i, entry)| {
+                size += entry.data().len();
+                *i == 0 || size <= max_bytes
+            })
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        Ok(batch)
+    }
+
+    /// Drops the entries at and after `index` to resolve a conflicting
+    /// suffix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is at or below the commit index. A committed entry
+    /// can never conflict with the leader's log, so such a call is a bug.
+    pub fn truncate_from(&mut self, index: usize) {
+        assert!(
+            index > self.committed,
+            "truncating committed entry {} (committed {})",
+            index,
+            self.committed
+        );
+        if index > self.last_index() {
+            return;
+        }
+        self.entries.truncate(index - self.first_index());
+    }
+
+    /// Term of the entry at `index`, if it is still known. Index 0 has term 0
+    /// and the snapshot point keeps its term after compaction.
+    pub fn term(&self, index: usize) -> Option<usize> {
+        if index == self.snapshot.index {
+            return Some(self.snapshot.term);
+        }
+        if index < self.first_index() || index > self.last_index() {
+            return None;
+        }
+        Some(self.entries[index - self.first_index()].seq_id().term())
+    }
+
+    /// Whether the log holds an entry at `prev_index` of `prev_term`, the
+    /// consistency check of AppendEntries.
+    ///
+    /// Index 0 always matches. Indices before the snapshot point are
+    /// committed, so they match whatever term the leader claims.
+    pub fn matches(&self, prev_index: usize, prev_term: usize) -> bool {
+        if prev_index == 0 || prev_index < self.snapshot.index {
+            return true;
+        }
+        self.term(prev_index) == Some(prev_term)
+    }
+
+    /// `SequenceID` of the last entry covered by the snapshot.
+    pub fn snapshot_seq_id(&self) -> SequenceID {
+        self.snapshot
+    }
+
+    pub fn committed(&self) -> usize {
+        self.committed
+    }
+
+    pub fn applied(&self) -> usize {
+        self.applied
+    }
+
+    /// Advances the commit index, never past the end of the log nor backwards.
+    pub fn commit_to(&mut self, index: usize) {
+        self.committed = self.committed.max(index.min(self.last_index()));
+    }
+
+    /// Commits up to `index` if the entry there is from `current_term`, as
+    /// a leader may do once a quorum has replicated `index`.
+    ///
+    /// Entries from earlier terms are never committed by counting replicas
+    /// (Raft §5.4.2): such an entry can still be overwritten by a leader
+    /// that never saw it, even when a majority holds it. They become
+    /// committed along with the first current-term entry after them.
+    pub fn maybe_commit(&mut self, index: usize, current_term: usize) -> bool {
+        if index <= self.committed || self.term(index) != Some(current_term) {
+            return false;
+        }
+        self.commit_to(index);
+        true
+    }
+
+    /// Advances the applied index, never past the commit index nor backwards.
+    pub fn apply_to(&mut self, index: usize) {
+        self.applied = self.applied.max(index.min(self.committed));
+    }
+
+    /// Discards entries up to `index` into the snapshot. Only applied
+    /// entries are discarded; a smaller `index` than the current snapshot
+    /// is a no-op.
+    pub fn compact(&mut self, index: usize) {
+        let index = index.min(self.applied);
+        if index <= self.snapshot.index {
+            return;
+        }
+        let offset = index - self.snapshot.index;
+        self.snapshot = self.entries[offset - 1].seq_id();
+        self.entries.drain(..offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{Endpoint, Membership};
+
+    fn logger(n: usize) -> Logger {
+        let mut logger = Logger::new();
+        for i in 0..n {
+            logger.append(1 + i / 4, vec![i as u8]);
+        }
+        logger
+    }
+
+    #[test]
+    fn test_sequence_id_order() {
+        assert!(SequenceID::new(1, 5) < SequenceID::new(2, 1));
+        assert!(SequenceID::new(2, 1) < SequenceID::new(2, 2));
+        assert_eq!(SequenceID::default(), SequenceID::new(0, 0));
+    }
+
+    #[test]
+    fn test_bounds_empty() {
+        let logger = Logger::new();
+        assert_eq!(logger.first_index(), 1);
+        assert_eq!(logger.last_index(), 0);
+        assert_eq!(logger.last_seq_id(), SequenceID::default());
+    }
+
+    #[test]
+    fn test_bounds_populated() {
+        let logger = logger(10);
+        assert_eq!(logger.first_index(), 1);
+        assert_eq!(logger.last_index(), 10);
+        assert_eq!(logger.last_seq_id(), SequenceID::new(3, 10));
+    }
+
+    #[test]
+    fn test_bounds_compacted() {
+        let mut logger = logger(10);
+        logger.commit_to(8);
+        logger.apply_to(6);
+        logger.compact(9);
+        assert_eq!(logger.snapshot_seq_id(), SequenceID::new(2, 6));
+        assert_eq!(logger.first_index(), 7);
+        assert_eq!(logger.last_index(), 10);
+
+        logger.compact(3);
+        assert_eq!(logger.first_index(), 7);
+
+        logger.apply_to(10);
+        assert_eq!(logger.applied(), 8);
+        logger.commit_to(10);
+        logger.apply_to(10);
+        logger.compact(10);
+        assert_eq!(logger.first_index(), 11);
+        assert_eq!(logger.last_index(), 10);
+        assert_eq!(logger.last_seq_id(), SequenceID::new(3, 10));
+
+        assert_eq!(logger.append(4, vec![]), SequenceID::new(4, 11));
+        assert_eq!(logger.first_index(), 11);
+        assert_eq!(logger.last_index(), 11);
+    }
+
+    #[test]
+    fn test_entries() {
+        let logger = logger(10);
+        let indices = |entries: Vec<Entry>| -> Vec<usize> {
+            entries.iter().map(|e| e.seq_id().index()).collect()
+        };
+        assert_eq!(
+            indices(logger.entries(1, 4, NO_LIMIT).unwrap()),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            indices(logger.entries(8, 100, NO_LIMIT).unwrap()),
+            vec![8, 9, 10]
+        );
+        assert_eq!(logger.entries(3, 3, NO_LIMIT).unwrap(), vec![]);
+        assert_eq!(logger.entries(5, 2, NO_LIMIT).unwrap(), vec![]);
+        assert_eq!(logger.entries(11, 20, NO_LIMIT).unwrap(), vec![]);
+        assert!(matches!(
+            logger.entries(0, 2, NO_LIMIT),
+            Err(Error::Compacted { first_index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_entries_max_bytes() {
+        let mut logger = Logger::new();
+        for size in [10, 10, 10, 30, 10].iter() {
+            logger.append(1, vec![0; *size]);
+        }
+        let indices = |entries: Vec<Entry>| -> Vec<usize> {
+            entries.iter().map(|e| e.seq_id().index()).collect()
+        };
+        assert_eq!(indices(logger.entries(1, 6, 25).unwrap()), vec![1, 2]);
+        assert_eq!(indices(logger.entries(1, 6, 30).unwrap()), vec![1, 2, 3]);
+        assert_eq!(indices(logger.entries(4, 6, 5).unwrap()), vec![4]);
+        assert_eq!(indices(logger.entries(1, 6, 0).unwrap()), vec![1]);
+        assert_eq!(logger.entries(1, 6, NO_LIMIT).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_catch_up_in_bounded_batches() {
+        let mut logger = Logger::new();
+        for _ in 0..10_000 {
+            logger.append(1, vec![0; 100]);
+        }
+        let config = crate::Config {
+            max_size_per_msg: 4096,
+            ..crate::Config::default()
+        };
+
+        let mut next = 1;
+        let mut messages = 0;
+        while next <= logger.last_index() {
+            let batch = logger
+                .entries(
+                    next,
+                    next + config.max_entries_per_msg,
+                    config.max_size_per_msg,
+                )
+                .unwrap();
+            assert!(batch.len() <= config.max_entries_per_msg);
+            assert!(batch.iter().map(|e| e.data().len()).sum::<usize>() <= config.max_size_per_msg);
+            next = batch.last().unwrap().seq_id().index() + 1;
+            messages += 1;
+        }
+        assert_eq!(messages, 250);
+    }
+
+    #[test]
+    fn test_entries_below_snapshot() {
+        let mut logger = logger(10);
+        logger.commit_to(5);
+        logger.apply_to(5);
+        logger.compact(5);
+        assert!(matches!(
+            logger.entries(5, 8, NO_LIMIT),
+            Err(Error::Compacted { first_index: 6 })
+        ));
+        assert!(matches!(
+            logger.entries(1, 2, NO_LIMIT),
+            Err(Error::Compacted { first_index: 6 })
+        ));
+        let entries = logger.entries(6, 8, NO_LIMIT).unwrap();
+        assert_eq!(entries[0], Entry::new(SequenceID::new(2, 6), vec![5]));
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn test_term() {
+        let mut logger = logger(10);
+        assert_eq!(logger.term(0), Some(0));
+        assert_eq!(logger.term(4), Some(1));
+        assert_eq!(logger.term(5), Some(2));
+        assert_eq!(logger.term(11), None);
+
+        logger.commit_to(5);
+        logger.apply_to(5);
+        logger.compact(5);
+        assert_eq!(logger.term(4), None);
+        assert_eq!(logger.term(5), Some(2));
+        assert_eq!(logger.term(6), Some(2));
+    }
+
+    #[test]
+    fn test_matches_empty() {
+        let logger = Logger::new();
+        assert!(logger.matches(0, 0));
+        assert!(logger.matches(0, 3));
+        assert!(!logger.matches(1, 1));
+    }
+
+    #[test]
+    fn test_matches_populated() {
+        let logger = logger(10);
+        assert!(logger.matches(0, 0));
+        assert!(logger.matches(4, 1));
+        assert!(!logger.matches(4, 2));
+        assert!(logger.matches(10, 3));
+        assert!(!logger.matches(11, 3));
+    }
+
+    #[test]
+    fn test_matches_compacted() {
+        let mut logger = logger(10);
+        logger.commit_to(6);
+        logger.apply_to(6);
+        logger.compact(6);
+        assert!(logger.matches(0, 0));
+        assert!(logger.matches(3, 1));
+        assert!(logger.matches(6, 2));
+        assert!(!logger.matches(6, 1));
+        assert!(logger.matches(7, 2));
+        assert!(!logger.matches(7, 3));
+        assert!(!logger.matches(11, 3));
+    }
+
+    #[test]
+    fn test_truncate_tail_is_noop() {
+        let mut logger = logger(5);
+        logger.truncate_from(6);
+        logger.truncate_from(100);
+        assert_eq!(logger.last_index(), 5);
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        let mut logger = logger(10);
+        logger.commit_to(3);
+        logger.truncate_from(6);
+        assert_eq!(logger.last_index(), 5);
+        assert_eq!(logger.last_seq_id(), SequenceID::new(2, 5));
+        assert_eq!(logger.append(4, vec![]), SequenceID::new(4, 6));
+        assert_eq!(logger.committed(), 3);
+    }
+
+    #[test]
+    fn test_truncate_after_compaction() {
+        let mut logger = logger(10);
+        logger.commit_to(4);
+        logger.apply_to(4);
+        logger.compact(4);
+        logger.truncate_from(5);
+        assert_eq!(logger.first_index(), 5);
+        assert_eq!(logger.last_index(), 4);
+        assert_eq!(logger.last_seq_id(), SequenceID::new(1, 4));
+    }
+
+    #[test]
+    #[should_panic(expected = "truncating committed entry 3 (committed 6)")]
+    fn test_truncate_never_drops_committed() {
+        let mut logger = logger(10);
+        logger.commit_to(6);
+        logger.truncate_from(7);
+        assert_eq!(logger.last_index(), 6);
+        logger.truncate_from(3);
+    }
+
+    #[test]
+    fn test_maybe_commit_current_term_only() {
+        // Raft figure 8: S1 leads term 2 and replicates index 2 to S2 only.
+        // S5 wins term 3 and writes index 2 locally. S1 wins term 4 and
+        // brings index 2 (term 2) to a majority: S1, S2 and S3.
+        let hosts: Vec<Endpoint> = (0..5).map(|i| Endpoint::new("s", 1 + i).unwrap()).collect();
+        let membership = Membership::new(hosts.clone()).unwrap();
+
+        let mut s1 = Logger::new();
+        s1.append(1, vec![]);
+        s1.append(2, vec![]);
+        s1.commit_to(1);
+
+        let matched = |indices: [usize; 5]| -> HashMap<Endpoint, usize> {
+            hosts.iter().cloned().zip(indices.iter().copied()).collect()
+        };
+        let quorum = membership.quorum_index(&matched([2, 2, 2, 1, 1]));
+        assert_eq!(quorum, 2);
+
+        // Counting replicas must not commit the term-2 entry, or S5 could
+        // still win term 5 and overwrite it with its term-3 entry.
+        assert!(!s1.maybe_commit(quorum, 4));
+        assert_eq!(s1.committed(), 1);
+
+        // Once a term-4 entry reaches a majority, both commit together.
+        s1.append(4, vec![]);
+        let quorum = membership.quorum_index(&matched([3, 3, 3, 1, 1]));
+        assert!(s1.maybe_commit(quorum, 4));
+        assert_eq!(s1.committed(), 3);
+        assert!(!s1.maybe_commit(3, 4));
+    }
+
+    #[test]
+    fn test_commit_to_clamps() {
+        let mut logger = logger(3);
+        logger.commit_to(5);
+        assert_eq!(logger.committed(), 3);
+        logger.commit_to(1);
+        assert_eq!(logger.committed(), 3);
+    }
+}
@@ -0,0 +1,845 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::snapshot::{put_bytes, Reader, SnapshotComponent};
+use crate::{Endpoint, Error, Result};
+
+/// How a member participates in the cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemberKind {
+    /// Replicates the log, votes, campaigns and counts toward quorum.
+    Voter,
+    /// Replicates the log while catching up but neither votes nor counts
+    /// toward quorum until promoted.
+    Learner,
+    /// Permanent full replica that never votes, never campaigns and never
+    /// counts toward quorum. Observers are not promotable.
+    Observer,
+}
+
+impl fmt::Display for MemberKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemberKind::Voter => write!(f, "voter"),
+            MemberKind::Learner => write!(f, "learner"),
+            MemberKind::Observer => write!(f, "observer"),
+        }
+    }
+}
+
+/// Number of votes, counting a candidate's vote for itself, that form a
+/// strict majority of `cluster_size` voters.
+pub fn majority(cluster_size: usize) -> usize {
+    cluster_size / 2 + 1
+}
+
+/// Upper bounds on cluster size, checked whenever membership changes.
+///
+/// Limits are chosen at bootstrap and replicated with the membership, so
+/// every replica checks a change against the same bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MembershipLimits {
+    pub max_voters: usize,
+    /// Voters, learners and observers combined.
+    pub max_members: usize,
+}
+
+impl Default for MembershipLimits {
+    fn default() -> Self {
+        Self {
+            max_voters: 9,
+            max_members: 32,
+        }
+    }
+}
+
+/// The servers of the cluster and how each one participates.
+///
+/// Members change one server at a time, each change carried by a log entry.
+/// Any majority of the old configuration overlaps any majority of the new
+/// one, so a configuration takes effect as soon as its entry is appended
+/// rather than when it commits. That only holds while a single change is in
+/// flight, which the leader ensures with `check_change` before proposing.
+///
+/// Followers apply every appended entry, and remember the configuration
+/// before each uncommitted one so `truncate_from` can undo it.
+#[derive(Debug, Clone)]
+pub struct Membership {
+    members: BTreeMap<Endpoint, MemberKind>,
+    limits: MembershipLimits,
+    /// Entry index of each uncommitted change, with the members before it.
+    uncommitted: Vec<(usize, BTreeMap<Endpoint, MemberKind>)>,
+}
+
+/// Compares the current configuration and limits, skipping `uncommitted`:
+/// that is local bookkeeping for undoing a truncated suffix, and the same
+/// configuration may have been reached with or without changes pending.
+impl PartialEq for Membership {
+    fn eq(&self, other: &Self) -> bool {
+        self.members == other.members && self.limits == other.limits
+    }
+}
+
+impl Eq for Membership {}
+
+impl Membership {
+    /// Bootstraps a membership under the default limits.
+    pub fn new<I: IntoIterator<Item = Endpoint>>(voters: I) -> Result<Self> {
+        Self::with_limits(voters, MembershipLimits::default())
+    }
+
+    /// Bootstraps a membership, rejecting an empty voter set or one beyond
+    /// `limits`. The limits are saved with the members and adopted by every
+    /// node that restores them.
+    pub fn with_limits<I: IntoIterator<Item = Endpoint>>(
+        voters: I,
+        limits: MembershipLimits,
+    ) -> Result<Self> {
+        let membership = Self {
+            members: voters
+                .into_iter()
+                .map(|endpoint| (endpoint, MemberKind::Voter))
+                .collect(),
+            limits,
+            uncommitted: Vec::new(),
+        };
+        if membership.is_empty() {
+            return Err(Error::EmptyMembership);
+        }
+        membership.check_limits(0, 0)?;
+        Ok(membership)
+    }
+
+    pub fn limits(&self) -> MembershipLimits {
+        self.limits
+    }
+
+    /// Whether a change has been applied but its entry not yet committed.
+    pub fn pending_change(&self) -> bool {
+        !self.uncommitted.is_empty()
+    }
+
+    /// Checks that a leader may propose a change now: only one change may
+    /// be uncommitted at a time. Entries appended from a leader's log are
+    /// applied without this check, since they were checked when proposed.
+    pub fn check_change(&self) -> Result<()> {
+        if self.pending_change() {
+            return Err(Error::ChangePending);
+        }
+        Ok(())
+    }
+
+    /// The log has committed up to `index`; changes at or before it can no
+    /// longer be undone.
+    pub fn commit_to(&mut self, index: usize) {
+        self.uncommitted.retain(|(at, _)| *at > index);
+    }
+
+    /// Undoes the changes whose entries at or after `index` were truncated
+    /// from the log, going back to the configuration before the first one.
+    pub fn truncate_from(&mut self, index: usize) {
+        if let Some(pos) = self.uncommitted.iter().position(|(at, _)| *at >= index) {
+            self.members = self.uncommitted[pos].1.clone();
+            self.uncommitted.truncate(pos);
+        }
+    }
+
+    /// Remembers the members before the change carried by entry `index`.
+    ///
+    /// `truncate_from` relies on changes being recorded in log order.
+    fn record(&mut self, index: usize) {
+        if let Some((last, _)) = self.uncommitted.last() {
+            assert!(
+                index > *last,
+                "membership change at {} after one at {}",
+                index,
+                last
+            );
+        }
+        self.uncommitted.push((index, self.members.clone()));
+    }
+
+    /// Checks the limits as if `extra_voters` and `extra_members` were added.
+    ///
+    /// Both the members and the limits are replicated, so every replica
+    /// reaches the same verdict for the same change.
+    fn check_limits(&self, extra_voters: usize, extra_members: usize) -> Result<()> {
+        if self.voters().count() + extra_voters > self.limits.max_voters {
+            return Err(Error::TooManyVoters(self.limits.max_voters));
+        }
+        if self.members.len() + extra_members > self.limits.max_members {
+            return Err(Error::TooManyMembers(self.limits.max_members));
+        }
+        Ok(())
+    }
+
+    /// An even number of voters tolerates no more failures than one voter
+    /// fewer, while needing a larger quorum.
+    pub fn has_even_voters(&self) -> bool {
+        self.voters().count().is_multiple_of(2)
+    }
+
+    pub fn voters(&self) -> impl Iterator<Item = &Endpoint> {
+        self.members_of(MemberKind::Voter)
+    }
+
+    pub fn learners(&self) -> impl Iterator<Item = &Endpoint> {
+        self.members_of(MemberKind::Learner)
+    }
+
+    pub fn observers(&self) -> impl Iterator<Item = &Endpoint> {
+        self.members_of(MemberKind::Observer)
+    }
+
+    fn members_of(&self, kind: MemberKind) -> impl Iterator<Item = &Endpoint> {
+        self.members
+            .iter()
+            .filter(move |(_, k)| **k == kind)
+            .map(|(endpoint, _)| endpoint)
+    }
+
+    pub fn kind(&self, endpoint: &Endpoint) -> Option<MemberKind> {
+        self.members.get(endpoint).copied()
+    }
+
+    pub fn contains(&self, endpoint: &Endpoint) -> bool {
+        self.members.contains_key(endpoint)
+    }
+
+    /// Whether `endpoint` may start an election or grant votes.
+    pub fn is_voter(&self, endpoint: &Endpoint) -> bool {
+        self.kind(endpoint) == Some(MemberKind::Voter)
+    }
+
+    /// Number of members of any kind.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Number of voters needed for a majority of the current configuration.
+    pub fn quorum(&self) -> usize {
+        majority(self.voters().count())
+    }
+
+    /// Whether the distinct voters among `acks` form a majority.
+    pub fn has_quorum<'a, I: IntoIterator<Item = &'a Endpoint>>(&self, acks: I) -> bool {
+        let acked: BTreeSet<&Endpoint> = acks
+            .into_iter()
+            .filter(|endpoint| self.is_voter(endpoint))
+            .collect();
+        acked.len() >= self.quorum()
+    }
+
+    /// Highest log index that a quorum of voters have replicated, given each
+    /// member's matched index. The leader includes itself in `matched`;
+    /// voters missing from it count as having nothing.
+    ///
+    /// This is only a candidate for the commit index: see
+    /// `Logger::maybe_commit` for the current-term rule.
+    pub fn quorum_index(&self, matched: &HashMap<Endpoint, usize>) -> usize {
+        let mut indices: Vec<usize> = self
+            .voters()
+            .map(|endpoint| matched.get(endpoint).copied().unwrap_or(0))
+            .collect();
+        if indices.is_empty() {
+            return 0;
+        }
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices[self.quorum() - 1]
+    }
+
+    /// Applies the change carried by the entry at `index`, as do the other
+    /// mutators below.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not past the last uncommitted change. Entries
+    /// are applied in log order, so such a call is a bug.
+    pub fn add_server(&mut self, index: usize, endpoint: Endpoint) -> Result<()> {
+        self.add(index, endpoint, MemberKind::Voter)
+    }
+
+    pub fn add_learner(&mut self, index: usize, endpoint: Endpoint) -> Result<()> {
+        self.add(index, endpoint, MemberKind::Learner)
+    }
+
+    pub fn add_observer(&mut self, index: usize, endpoint: Endpoint) -> Result<()> {
+        self.add(index, endpoint, MemberKind::Observer)
+    }
+
+    fn add(&mut self, index: usize, endpoint: Endpoint, kind: MemberKind) -> Result<()> {
+        if self.members.contains_key(&endpoint) {
+            return Err(Error::MemberExists(endpoint));
+        }
+        self.check_limits((kind == MemberKind::Voter) as usize, 1)?;
+        self.record(index);
+        self.members.insert(endpoint, kind);
+        Ok(())
+    }
+
+    /// Turns a caught-up learner into a voter. Observers are never promoted.
+    pub fn promote_learner(&mut self, index: usize, endpoint: &Endpoint) -> Result<()> {
+        match self.kind(endpoint) {
+            None => return Err(Error::MemberNotFound(endpoint.clone())),
+            Some(MemberKind::Learner) => self.check_limits(1, 0)?,
+            Some(_) => return Err(Error::NotLearner(endpoint.clone())),
+        }
+        self.record(index);
+        self.members.insert(endpoint.clone(), MemberKind::Voter);
+        Ok(())
+    }
+
+    /// Removes a member of any kind, refusing to remove the last voter.
+    pub fn remove_server(&mut self, index: usize, endpoint: &Endpoint) -> Result<()> {
+        match self.kind(endpoint) {
+            None => return Err(Error::MemberNotFound(endpoint.clone())),
+            Some(MemberKind::Voter) if self.voters().count() == 1 => {
+                return Err(Error::EmptyMembership)
+            }
+            Some(_) => {}
+        }
+        self.record(index);
+        self.members.remove(endpoint);
+        Ok(())
+    }
+}
+
+impl SnapshotComponent for Membership {
+    fn name(&self) -> &str {
+        "membership"
+    }
+
+    /// Saves the configuration as of the last committed change. Uncommitted
+    /// changes are re-applied from the log after the snapshot point, where
+    /// they can still be undone.
+    fn save(&self) -> Vec<u8> {
+        let committed = self
+            .uncommitted
+            .first()
+            .map_or(&self.members, |(_, members)| members);
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(self.limits.max_voters as u64).to_be_bytes());
+        buf.extend_from_slice(&(self.limits.max_members as u64).to_be_bytes());
+        for (endpoint, kind) in committed {
+            buf.push(match kind {
+                MemberKind::Voter => 0,
+                MemberKind::Learner => 1,
+                MemberKind::Observer => 2,
+            });
+            put_bytes(&mut buf, endpoint.to_string().as_bytes());
+        }
+        buf
+    }
+
+    fn validate(&self, data: Option<&[u8]>) -> Result<()> {
+        self.decode_restored(data).map(|_| ())
+    }
+
+    fn restore(&mut self, data: Option<&[u8]>) -> Result<()> {
+        let restored = self.decode_restored(data)?;
+        self.members = restored.members;
+        self.limits = restored.limits;
+        self.uncommitted.clear();
+        Ok(())
+    }
+}
+
+impl Membership {
+    /// Decodes a membership section, holding it to the same rules as a
+    /// change made through `add_server`: at least one voter and within the
+    /// limits saved alongside the members.
+    fn decode_restored(&self, data: Option<&[u8]>) -> Result<Membership> {
+        let restored = decode_membership(data)?;
+        if restored.voters().next().is_none() {
+            return Err(Error::EmptyMembership);
+        }
+        restored.check_limits(0, 0)?;
+        Ok(restored)
+    }
+}
+
+fn read_limit(reader: &mut Reader<'_>) -> Result<usize> {
+    usize::try_from(reader.u64()?).map_err(|_| Error::CorruptSnapshot("limit out of range"))
+}
+
+fn decode_membership(data: Option<&[u8]>) -> Result<Membership> {
+    let data = data.ok_or(Error::CorruptSnapshot("missing membership section"))?;
+    let mut reader = Reader::new(data);
+    let limits = MembershipLimits {
+        max_voters: read_limit(&mut reader)?,
+        max_members: read_limit(&mut reader)?,
+    };
+    let mut members = BTreeMap::new();
+    while !reader.is_empty() {
+        let kind = match reader.u8()? {
+            0 => MemberKind::Voter,
+            1 => MemberKind::Learner,
+            2 => MemberKind::Observer,
+            _ => return Err(Error::CorruptSnapshot("unknown member kind")),
+        };
+        let endpoint = std::str::from_utf8(reader.bytes()?)
+            .map_err(|_| Error::CorruptSnapshot("endpoint is not utf-8"))?
+            .parse()?;
+        if members.insert(endpoint, kind).is_some() {
+            return Err(Error::CorruptSnapshot("duplicate member"));
+        }
+    }
+    Ok(Membership {
+        members,
+        limits,
+        uncommitted: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Snapshot;
+
+    fn endpoints(n: usize) -> Vec<Endpoint> {
+        (0..n)
+            .map(|i| Endpoint::new("127.0.0.1", 8080 + i as u16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_majority() {
+        // (voters including self, votes needed, failures tolerated)
+        for (size, needed, tolerated) in [
+            (1, 1, 0),
+            (2, 2, 0),
+            (3, 2, 1),
+            (4, 3, 1),
+            (5, 3, 2),
+            (6, 4, 2),
+            (7, 4, 3),
+        ]
+        .iter()
+        {
+            assert_eq!(majority(*size), *needed, "cluster of {}", size);
+            assert_eq!(size - majority(*size), *tolerated, "cluster of {}", size);
+            assert!(2 * majority(*size) > *size);
+        }
+    }
+
+    #[test]
+    fn test_grow_and_shrink() {
+        let hosts = endpoints(5);
+        let mut membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        assert_eq!(membership.quorum(), 2);
+
+        membership.add_server(1, hosts[3].clone()).unwrap();
+        assert_eq!(membership.len(), 4);
+        assert_eq!(membership.quorum(), 3);
+
+        membership.add_server(2, hosts[4].clone()).unwrap();
+        assert_eq!(membership.len(), 5);
+        assert_eq!(membership.quorum(), 3);
+        assert!(!membership.has_quorum(&hosts[..2]));
+        assert!(membership.has_quorum(&hosts[2..]));
+
+        membership.remove_server(3, &hosts[4]).unwrap();
+        membership.remove_server(4, &hosts[3]).unwrap();
+        assert_eq!(membership, Membership::new(hosts[..3].to_vec()).unwrap());
+        assert_eq!(membership.quorum(), 2);
+    }
+
+    #[test]
+    fn test_invalid_changes() {
+        let hosts = endpoints(2);
+        let mut membership = Membership::new(hosts[..1].to_vec()).unwrap();
+
+        assert!(matches!(
+            membership.add_server(1, hosts[0].clone()),
+            Err(Error::MemberExists(v)) if v == hosts[0]
+        ));
+        assert!(matches!(
+            membership.remove_server(2, &hosts[1]),
+            Err(Error::MemberNotFound(v)) if v == hosts[1]
+        ));
+        assert!(matches!(
+            membership.remove_server(3, &hosts[0]),
+            Err(Error::EmptyMembership)
+        ));
+    }
+
+    #[test]
+    fn test_quorum_ignores_non_members() {
+        let hosts = endpoints(4);
+        let membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        assert!(!membership.has_quorum(&[hosts[0].clone(), hosts[3].clone()]));
+        assert!(!membership.has_quorum(&[hosts[0].clone(), hosts[0].clone()]));
+    }
+
+    #[test]
+    fn test_observer_never_counts() {
+        let hosts = endpoints(4);
+        let mut membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        membership.add_observer(1, hosts[3].clone()).unwrap();
+
+        assert_eq!(membership.len(), 4);
+        assert_eq!(membership.quorum(), 2);
+        assert_eq!(membership.kind(&hosts[3]), Some(MemberKind::Observer));
+        assert!(!membership.is_voter(&hosts[3]));
+        assert_eq!(membership.observers().collect::<Vec<_>>(), vec![&hosts[3]]);
+        assert!(!membership.has_quorum(&[hosts[0].clone(), hosts[3].clone()]));
+        assert!(membership.has_quorum(&hosts[..2]));
+        assert!(matches!(
+            membership.add_server(2, hosts[3].clone()),
+            Err(Error::MemberExists(v)) if v == hosts[3]
+        ));
+
+        membership.remove_server(3, &hosts[3]).unwrap();
+        assert_eq!(membership, Membership::new(hosts[..3].to_vec()).unwrap());
+    }
+
+    #[test]
+    fn test_last_voter_with_observers() {
+        let hosts = endpoints(2);
+        let mut membership = Membership::new(hosts[..1].to_vec()).unwrap();
+        membership.add_observer(1, hosts[1].clone()).unwrap();
+        assert!(matches!(
+            membership.remove_server(2, &hosts[0]),
+            Err(Error::EmptyMembership)
+        ));
+    }
+
+    #[test]
+    fn test_one_change_at_a_time() {
+        let hosts = endpoints(5);
+        let mut membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        membership.check_change().unwrap();
+        membership.add_server(4, hosts[3].clone()).unwrap();
+        assert!(membership.pending_change());
+        assert!(matches!(
+            membership.check_change(),
+            Err(Error::ChangePending)
+        ));
+
+        // A follower replays whatever the leader's log holds.
+        membership.add_learner(5, hosts[4].clone()).unwrap();
+        membership.commit_to(4);
+        assert!(matches!(
+            membership.check_change(),
+            Err(Error::ChangePending)
+        ));
+        membership.commit_to(5);
+        membership.check_change().unwrap();
+        assert_eq!(membership.voters().count(), 4);
+        assert_eq!(membership.learners().count(), 1);
+    }
+
+    #[test]
+    fn test_truncate_reverts_uncommitted_changes() {
+        let hosts = endpoints(5);
+        let mut membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        membership.add_server(4, hosts[3].clone()).unwrap();
+        membership.commit_to(4);
+        let committed = membership.clone();
+
+        membership.add_server(6, hosts[4].clone()).unwrap();
+        membership.remove_server(7, &hosts[0]).unwrap();
+        membership.truncate_from(7);
+        assert!(membership.contains(&hosts[0]));
+        assert!(membership.is_voter(&hosts[4]));
+
+        // A new leader overwrote index 6 onwards.
+        membership.truncate_from(5);
+        assert_eq!(membership, committed);
+        assert!(!membership.pending_change());
+        membership.truncate_from(1);
+        assert_eq!(membership, committed);
+    }
+
+    #[test]
+    #[should_panic(expected = "membership change at 5 after one at 6")]
+    fn test_changes_out_of_order() {
+        let hosts = endpoints(5);
+        let mut membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        membership.add_server(6, hosts[3].clone()).unwrap();
+        membership.add_learner(5, hosts[4].clone()).unwrap();
+    }
+
+    #[test]
+    fn test_equality_includes_limits() {
+        let hosts = endpoints(3);
+        let limits = MembershipLimits {
+            max_voters: 3,
+            max_members: 4,
+        };
+        let membership = Membership::new(hosts.clone()).unwrap();
+        assert_ne!(
+            membership,
+            Membership::with_limits(hosts.clone(), limits).unwrap()
+        );
+
+        let learner = endpoints(4).pop().unwrap();
+        let mut pending = membership.clone();
+        pending.add_learner(1, learner.clone()).unwrap();
+        let mut committed = membership;
+        committed.add_learner(1, learner).unwrap();
+        committed.commit_to(1);
+        assert_eq!(pending, committed);
+    }
+
+    #[test]
+    fn test_learner_promotion() {
+        let hosts = endpoints(4);
+        let mut membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        membership.add_learner(1, hosts[3].clone()).unwrap();
+
+        assert_eq!(membership.quorum(), 2);
+        assert_eq!(membership.learners().collect::<Vec<_>>(), vec![&hosts[3]]);
+        assert!(!membership.is_voter(&hosts[3]));
+        assert!(!membership.has_quorum(&[hosts[0].clone(), hosts[3].clone()]));
+
+        membership.promote_learner(2, &hosts[3]).unwrap();
+        assert_eq!(membership.kind(&hosts[3]), Some(MemberKind::Voter));
+        assert_eq!(membership.quorum(), 3);
+        assert!(!membership.has_quorum(&hosts[2..]));
+        assert!(membership.has_quorum(&hosts[1..]));
+        assert_eq!(membership.learners().count(), 0);
+    }
+
+    #[test]
+    fn test_promote_non_learner() {
+        let hosts = endpoints(3);
+        let mut membership = Membership::new(hosts[..1].to_vec()).unwrap();
+        membership.add_observer(1, hosts[1].clone()).unwrap();
+
+        assert!(matches!(
+            membership.promote_learner(2, &hosts[0]),
+            Err(Error::NotLearner(v)) if v == hosts[0]
+        ));
+        assert!(matches!(
+            membership.promote_learner(3, &hosts[1]),
+            Err(Error::NotLearner(v)) if v == hosts[1]
+        ));
+        assert!(matches!(
+            membership.promote_learner(4, &hosts[2]),
+            Err(Error::MemberNotFound(v)) if v == hosts[2]
+        ));
+    }
+
+    #[test]
+    fn test_limits() {
+        let hosts = endpoints(5);
+        let limits = MembershipLimits {
+            max_voters: 3,
+            max_members: 4,
+        };
+        assert!(matches!(
+            Membership::with_limits(hosts[..4].to_vec(), limits),
+            Err(Error::TooManyVoters(3))
+        ));
+
+        let mut membership = Membership::with_limits(hosts[..3].to_vec(), limits).unwrap();
+        assert!(matches!(
+            membership.add_server(1, hosts[3].clone()),
+            Err(Error::TooManyVoters(3))
+        ));
+        membership.add_learner(2, hosts[3].clone()).unwrap();
+        assert!(matches!(
+            membership.promote_learner(3, &hosts[3]),
+            Err(Error::TooManyVoters(3))
+        ));
+        assert_eq!(membership.kind(&hosts[3]), Some(MemberKind::Learner));
+        assert!(matches!(
+            membership.add_observer(4, hosts[4].clone()),
+            Err(Error::TooManyMembers(4))
+        ));
+        assert!(!membership.contains(&hosts[4]));
+    }
+
+    #[test]
+    fn test_default_limits() {
+        let hosts = endpoints(10);
+        assert!(Membership::with_limits(hosts[..9].to_vec(), MembershipLimits::default()).is_ok());
+        assert!(matches!(
+            Membership::with_limits(hosts, MembershipLimits::default()),
+            Err(Error::TooManyVoters(9))
+        ));
+    }
+
+    #[test]
+    fn test_bootstrap_is_validated() {
+        assert!(matches!(
+            Membership::new(Vec::new()),
+            Err(Error::EmptyMembership)
+        ));
+        assert!(matches!(
+            Membership::with_limits(Vec::new(), MembershipLimits::default()),
+            Err(Error::EmptyMembership)
+        ));
+        assert!(matches!(
+            Membership::new(endpoints(20)),
+            Err(Error::TooManyVoters(9))
+        ));
+    }
+
+    #[test]
+    fn test_even_voters() {
+        let hosts = endpoints(4);
+        let mut membership = Membership::new(hosts[..3].to_vec()).unwrap();
+        assert!(!membership.has_even_voters());
+        membership.add_observer(1, hosts[3].clone()).unwrap();
+        assert!(!membership.has_even_voters());
+        membership.remove_server(2, &hosts[3]).unwrap();
+        membership.add_server(3, hosts[3].clone()).unwrap();
+        assert!(membership.has_even_voters());
+    }
+
+    #[test]
+    fn test_quorum_index() {
+        let hosts = endpoints(6);
+        let mut membership = Membership::new(hosts[..5].to_vec()).unwrap();
+        membership.add_observer(1, hosts[5].clone()).unwrap();
+        let matched: HashMap<Endpoint, usize> = hosts
+            .iter()
+            .cloned()
+            .zip(vec![9, 7, 5, 3, 1, 100])
+            .collect();
+        assert_eq!(membership.quorum_index(&matched), 5);
+
+        let mut partial = matched.clone();
+        partial.remove(&hosts[0]);
+        partial.remove(&hosts[1]);
+        assert_eq!(membership.quorum_index(&partial), 1);
+        assert_eq!(membership.quorum_index(&HashMap::new()), 0);
+    }
+
+    /// Builds a membership section by hand, bypassing the checks that
+    /// `save` would only ever see passed.
+    fn section(limits: MembershipLimits, members: &[(u8, &Endpoint)]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(limits.max_voters as u64).to_be_bytes());
+        buf.extend_from_slice(&(limits.max_members as u64).to_be_bytes());
+        for (kind, endpoint) in members {
+            buf.push(*kind);
+            put_bytes(&mut buf, endpoint.to_string().as_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_restore_adopts_limits() {
+        let hosts = endpoints(3);
+        let limits = MembershipLimits {
+            max_voters: 3,
+            max_members: 4,
+        };
+        let mut snapshot = Snapshot::new();
+        snapshot.capture(&[&Membership::with_limits(hosts.clone(), limits).unwrap()]);
+
+        let mut membership = Membership::new(hosts[..1].to_vec()).unwrap();
+        snapshot.install(&mut [&mut membership]).unwrap();
+        assert_eq!(membership.limits(), limits);
+        assert_eq!(membership.voters().count(), 3);
+    }
+
+    #[test]
+    fn test_snapshot_leaves_out_uncommitted_changes() {
+        let hosts = endpoints(4);
+        let mut leader = Membership::new(hosts[..3].to_vec()).unwrap();
+        leader.add_server(5, hosts[3].clone()).unwrap();
+        let mut snapshot = Snapshot::new();
+        snapshot.capture(&[&leader]);
+
+        let mut follower = Membership::new(hosts[..1].to_vec()).unwrap();
+        snapshot.install(&mut [&mut follower]).unwrap();
+        assert!(!follower.contains(&hosts[3]));
+        // The follower re-applies the change from the log.
+        follower.add_server(5, hosts[3].clone()).unwrap();
+        assert_eq!(follower, leader);
+
+        leader.truncate_from(5);
+        follower.truncate_from(5);
+        assert_eq!(follower, leader);
+        assert_eq!(follower.voters().count(), 3);
+    }
+
+    #[test]
+    fn test_restore_checks_limits() {
+        let hosts = endpoints(5);
+        let voters: Vec<(u8, &Endpoint)> = hosts.iter().map(|host| (0, host)).collect();
+        let mut membership = Membership::new(hosts[..1].to_vec()).unwrap();
+        let mut snapshot = Snapshot::new();
+
+        let limits = MembershipLimits {
+            max_voters: 1,
+            max_members: 8,
+        };
+        snapshot.set_section("membership", section(limits, &voters));
+        assert!(matches!(
+            snapshot.install(&mut [&mut membership]),
+            Err(Error::TooManyVoters(1))
+        ));
+        assert_eq!(membership.len(), 1);
+
+        let limits = MembershipLimits {
+            max_voters: 9,
+            max_members: 4,
+        };
+        snapshot.set_section("membership", section(limits, &voters));
+        assert!(matches!(
+            snapshot.install(&mut [&mut membership]),
+            Err(Error::TooManyMembers(4))
+        ));
+        assert_eq!(membership.len(), 1);
+        assert_eq!(membership.limits(), MembershipLimits::default());
+    }
+
+    #[test]
+    fn test_restore_requires_a_voter() {
+        let hosts = endpoints(2);
+        let mut membership = Membership::new(hosts[..1].to_vec()).unwrap();
+
+        let mut snapshot = Snapshot::new();
+        snapshot.set_section("membership", section(MembershipLimits::default(), &[]));
+        assert!(matches!(
+            snapshot.install(&mut [&mut membership]),
+            Err(Error::EmptyMembership)
+        ));
+
+        snapshot.set_section(
+            "membership",
+            section(MembershipLimits::default(), &[(1, &hosts[1])]),
+        );
+        assert!(matches!(
+            snapshot.install(&mut [&mut membership]),
+            Err(Error::EmptyMembership)
+        ));
+        assert_eq!(membership, Membership::new(hosts[..1].to_vec()).unwrap());
+    }
+
+    #[test]
+    fn test_restore_rejects_duplicate_members() {
+        let hosts = endpoints(2);
+        let mut membership = Membership::new(hosts[..1].to_vec()).unwrap();
+        let mut snapshot = Snapshot::new();
+        snapshot.set_section(
+            "membership",
+            section(
+                MembershipLimits::default(),
+                &[(0, &hosts[1]), (1, &hosts[1])],
+            ),
+        );
+        assert!(matches!(
+            snapshot.install(&mut [&mut membership]),
+            Err(Error::CorruptSnapshot("duplicate member"))
+        ));
+        assert_eq!(membership, Membership::new(hosts[..1].to_vec()).unwrap());
+    }
+
+    #[test]
+    fn test_kind_labels() {
+        assert_eq!(MemberKind::Voter.to_string(), "voter");
+        assert_eq!(MemberKind::Learner.to_string(), "learner");
+        assert_eq!(MemberKind::Observer.to_string(), "observer");
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::Endpoint;
+
+/// Receiver of a node's metrics.
+///
+/// Every method defaults to doing nothing, so implementations only override
+/// what they export. Methods are called on hot paths and must be cheap.
+pub trait Metrics: Send + Sync {
+    fn term(&self, _term: usize) {}
+
+    fn commit_index(&self, _index: usize) {}
+
+    fn applied_index(&self, _index: usize) {}
+
+    fn election_started(&self) {}
+
+    fn election_won(&self) {}
+
+    fn vote_granted(&self) {}
+
+    fn vote_denied(&self) {}
+
+    fn heartbeat_sent(&self) {}
+
+    fn heartbeat_failed(&self) {}
+
+    fn append_sent(&self) {}
+
+    fn append_rejected(&self) {}
+
+    fn snapshot_transferred(&self) {}
+
+    /// `peer` answered at `at`.
+    fn peer_contact(&self, _peer: &Endpoint, _at: Instant) {}
+
+    /// `peer` has left the membership; drop anything kept for it.
+    fn forget_peer(&self, _peer: &Endpoint) {}
+}
+
+/// Discards everything; the default when no metrics are wanted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// Keeps counters and gauges in atomics for polling via `snapshot()`.
+///
+/// Counters are relaxed atomic increments and allocation-free; only
+/// `peer_contact` takes a lock.
+#[derive(Debug, Default)]
+pub struct AtomicMetrics {
+    term: AtomicUsize,
+    commit_index: AtomicUsize,
+    applied_index: AtomicUsize,
+    elections_started: AtomicU64,
+    elections_won: AtomicU64,
+    votes_granted: AtomicU64,
+    votes_denied: AtomicU64,
+    heartbeats_sent: AtomicU64,
+    heartbeats_failed: AtomicU64,
+    append_sent: AtomicU64,
+    append_rejected: AtomicU64,
+    snapshots_transferred: AtomicU64,
+    peer_contact: Mutex<HashMap<Endpoint, Instant>>,
+}
+
+/// Point-in-time copy of `AtomicMetrics`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub term: usize,
+    pub commit_index: usize,
+    pub applied_index: usize,
+    pub elections_started: u64,
+    pub elections_won: u64,
+    pub votes_granted: u64,
+    pub votes_denied: u64,
+    pub heartbeats_sent: u64,
+    pub heartbeats_failed: u64,
+    pub append_sent: u64,
+    pub append_rejected: u64,
+    pub snapshots_transferred: u64,
+    /// Time since each peer last answered, as of the snapshot.
+    pub peer_contact: HashMap<Endpoint, Duration>,
+}
+
+fn incr(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+impl AtomicMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies the current values. Peer contact ages are measured up to
+    /// `now`, so a peer that stops answering keeps ageing.
+    pub fn snapshot(&self, now: Instant) -> MetricsSnapshot {
+        MetricsSnapshot {
+            term: self.term.load(Ordering::Relaxed),
+            commit_index: self.commit_index.load(Ordering::Relaxed),
+            applied_index: self.applied_index.load(Ordering::Relaxed),
+            elections_started: self.elections_started.load(Ordering::Relaxed),
+            elections_won: self.elections_won.load(Ordering::Relaxed),
+            votes_granted: self.votes_granted.load(Ordering::Relaxed),
+            votes_denied: self.votes_denied.load(Ordering::Relaxed),
+            heartbeats_sent: self.heartbeats_sent.load(Ordering::Relaxed),
+            heartbeats_failed: self.heartbeats_failed.load(Ordering::Relaxed),
+            append_sent: self.append_sent.load(Ordering::Relaxed),
+            append_rejected: self.append_rejected.load(Ordering::Relaxed),
+            snapshots_transferred: self.snapshots_transferred.load(Ordering::Relaxed),
+            peer_contact: self
+                .peer_contact
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(peer, at)| (peer.clone(), now.saturating_duration_since(*at)))
+                .collect(),
+        }
+    }
+}
+
+impl Metrics for AtomicMetrics {
+    fn term(&self, term: usize) {
+        self.term.store(term, Ordering::Relaxed);
+    }
+
+    fn commit_index(&self, index: usize) {
+        self.commit_index.store(index, Ordering::Relaxed);
+    }
+
+    fn applied_index(&self, index: usize) {
+        self.applied_index.store(index, Ordering::Relaxed);
+    }
+
+    fn election_started(&self) {
+        incr(&self.elections_started);
+    }
+
+    fn election_won(&self) {
+        incr(&self.elections_won);
+    }
+
+    fn vote_granted(&self) {
+        incr(&self.votes_granted);
+    }
+
+    fn vote_denied(&self) {
+        incr(&self.votes_denied);
+    }
+
+    fn heartbeat_sent(&self) {
+        incr(&self.heartbeats_sent);
+    }
+
+    fn heartbeat_failed(&self) {
+        incr(&self.heartbeats_failed);
+    }
+
+    fn append_sent(&self) {
+        incr(&self.append_sent);
+    }
+
+    fn append_rejected(&self) {
+        incr(&self.append_rejected);
+    }
+
+    fn snapshot_transferred(&self) {
+        incr(&self.snapshots_transferred);
+    }
+
+    fn peer_contact(&self, peer: &Endpoint, at: Instant) {
+        let mut peer_contact = self.peer_contact.lock().unwrap();
+        match peer_contact.get_mut(peer) {
+            Some(last) => *last = (*last).max(at),
+            None => {
+                peer_contact.insert(peer.clone(), at);
+            }
+        }
+    }
+
+    fn forget_peer(&self, peer: &Endpoint) {
+        self.peer_contact.lock().unwrap().remove(peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+    use crate::{Clock, SimClock};
+
+    #[test]
+    fn test_atomic_metrics() {
+        let clock = SimClock::new();
+        let metrics = AtomicMetrics::new();
+        assert_eq!(metrics.snapshot(clock.now()), MetricsSnapshot::default());
+
+        metrics.term(2);
+        metrics.election_started();
+        metrics.vote_granted();
+        metrics.vote_denied();
+        metrics.election_won();
+        metrics.heartbeat_sent();
+        metrics.commit_index(5);
+        metrics.applied_index(4);
+        metrics.append_rejected();
+
+        let snapshot = metrics.snapshot(clock.now());
+        assert_eq!(snapshot.term, 2);
+        assert_eq!(snapshot.commit_index, 5);
+        assert_eq!(snapshot.applied_index, 4);
+        assert_eq!(snapshot.elections_started, 1);
+        assert_eq!(snapshot.elections_won, 1);
+        assert_eq!(snapshot.votes_granted, 1);
+        assert_eq!(snapshot.votes_denied, 1);
+        assert_eq!(snapshot.heartbeats_sent, 1);
+        assert_eq!(snapshot.append_rejected, 1);
+    }
+
+    #[test]
+    fn test_silent_peer_keeps_ageing() {
+        let clock = SimClock::new();
+        let metrics = AtomicMetrics::new();
+        let peer = Endpoint::new("127.0.0.1", 8080).unwrap();
+        let first = clock.now();
+        clock.advance(Duration::from_millis(7));
+        metrics.peer_contact(&peer, clock.now());
+        // A late report of an older contact doesn't turn back the clock.
+        metrics.peer_contact(&peer, first);
+
+        clock.advance(Duration::from_millis(3));
+        let age = |now| metrics.snapshot(now).peer_contact[&peer];
+        assert_eq!(age(clock.now()), Duration::from_millis(3));
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(age(clock.now()), Duration::from_millis(5003));
+    }
+
+    #[test]
+    fn test_forget_peer() {
+        let metrics = AtomicMetrics::new();
+        let peers = [
+            Endpoint::new("127.0.0.1", 8080).unwrap(),
+            Endpoint::new("127.0.0.1", 8081).unwrap(),
+        ];
+        let now = Instant::now();
+        for peer in &peers {
+            metrics.peer_contact(peer, now);
+        }
+        metrics.forget_peer(&peers[0]);
+        metrics.forget_peer(&peers[0]);
+
+        let peer_contact = metrics.snapshot(now).peer_contact;
+        assert_eq!(peer_contact.len(), 1);
+        assert!(peer_contact.contains_key(&peers[1]));
+    }
+
+    #[test]
+    fn test_concurrent_increments() {
+        let metrics = Arc::new(AtomicMetrics::new());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let metrics = metrics.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        metrics.append_sent();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(metrics.snapshot(Instant::now()).append_sent, 4000);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::snapshot::{put_bytes, Reader, SnapshotComponent};
+use crate::{Entry, Error, Result};
+
+/// Per-client deduplication of applied requests.
+///
+/// Every replica applies the same entries in the same order, so each
+/// reaches the same verdict about which entries are retries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sessions {
+    /// Last applied `sequence_no` of each client and its result.
+    last: HashMap<u64, (u64, Vec<u8>)>,
+}
+
+impl Sessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies `entry` with `apply` unless its request has already been
+    /// applied.
+    ///
+    /// A retry of the client's latest request returns the cached result. A
+    /// retry of an older request returns `None`: its result was already
+    /// superseded, so the client cannot still be waiting for it.
+    pub fn apply<F>(&mut self, entry: &Entry, apply: F) -> Option<Vec<u8>>
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        let request = match entry.request() {
+            Some(request) => request,
+            None => return Some(apply(entry.data())),
+        };
+        if let Some((sequence_no, result)) = self.last.get(&request.client_id) {
+            if request.sequence_no == *sequence_no {
+                return Some(result.clone());
+            }
+            if request.sequence_no < *sequence_no {
+                return None;
+            }
+        }
+        let result = apply(entry.data());
+        self.last
+            .insert(request.client_id, (request.sequence_no, result.clone()));
+        Some(result)
+    }
+}
+
+impl SnapshotComponent for Sessions {
+    fn name(&self) -> &str {
+        "sessions"
+    }
+
+    fn save(&self) -> Vec<u8> {
+        let mut clients: Vec<_> = self.last.iter().collect();
+        clients.sort_by_key(|(client_id, _)| **client_id);
+        let mut buf = Vec::new();
+        for (client_id, (sequence_no, result)) in clients {
+            buf.extend_from_slice(&client_id.to_be_bytes());
+            buf.extend_from_slice(&sequence_no.to_be_bytes());
+            put_bytes(&mut buf, result);
+        }
+        buf
+    }
+
+    fn validate(&self, data: Option<&[u8]>) -> Result<()> {
+        decode_sessions(data).map(|_| ())
+    }
+
+    fn restore(&mut self, data: Option<&[u8]>) -> Result<()> {
+        self.last = decode_sessions(data)?;
+        Ok(())
+    }
+}
+
+fn decode_sessions(data: Option<&[u8]>) -> Result<HashMap<u64, (u64, Vec<u8>)>> {
+    let mut reader = Reader::new(data.unwrap_or_default());
+    let mut last = HashMap::new();
+    while !reader.is_empty() {
+        let client_id = reader.u64()?;
+        let sequence_no = reader.u64()?;
+        let session = (sequence_no, reader.bytes()?.to_vec());
+        if last.insert(client_id, session).is_some() {
+            return Err(Error::CorruptSnapshot("duplicate client session"));
+        }
+    }
+    Ok(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RequestID, SequenceID, Snapshot};
+
+    fn entry(index: usize, client_id: u64, sequence_no: u64) -> Entry {
+        Entry::new(SequenceID::new(1, index), vec![index as u8]).with_request(RequestID {
+            client_id,
+            sequence_no,
+        })
+    }
+
+    #[test]
+    fn test_retry_applies_once() {
+        let mut sessions = Sessions::new();
+        let mut applied = Vec::new();
+        let mut apply = |entry: &Entry| {
+            sessions.apply(entry, |data| {
+                applied.push(data[0]);
+                vec![data[0] * 10]
+            })
+        };
+
+        assert_eq!(apply(&entry(1, 7, 1)), Some(vec![10]));
+        assert_eq!(apply(&entry(2, 7, 1)), Some(vec![10]));
+        assert_eq!(apply(&entry(3, 8, 1)), Some(vec![30]));
+        assert_eq!(apply(&entry(4, 7, 2)), Some(vec![40]));
+        assert_eq!(apply(&entry(5, 7, 1)), None);
+        assert_eq!(applied, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_entries_without_request_always_apply() {
+        let mut sessions = Sessions::new();
+        let entry = Entry::new(SequenceID::new(1, 1), vec![1]);
+        let mut count = 0;
+        for _ in 0..2 {
+            sessions.apply(&entry, |_| {
+                count += 1;
+                vec![]
+            });
+        }
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut sessions = Sessions::new();
+        sessions.apply(&entry(1, 7, 3), |_| vec![1, 2]);
+        sessions.apply(&entry(2, 9, 1), |_| vec![]);
+
+        let mut snapshot = Snapshot::new();
+        snapshot.capture(&[&sessions]);
+        let mut restored = Sessions::new();
+        Snapshot::decode(&snapshot.encode())
+            .unwrap()
+            .install(&mut [&mut restored])
+            .unwrap();
+        assert_eq!(restored, sessions);
+        assert_eq!(
+            restored.apply(&entry(3, 7, 3), |_| unreachable!()),
+            Some(vec![1, 2])
+        );
+
+        Snapshot::new().install(&mut [&mut restored]).unwrap();
+        assert_eq!(restored, Sessions::new());
+    }
+
+    #[test]
+    fn test_restore_rejects_duplicate_clients() {
+        let mut sessions = Sessions::new();
+        sessions.apply(&entry(1, 7, 3), |_| vec![1]);
+
+        let mut section = Vec::new();
+        for sequence_no in [5u64, 2].iter() {
+            section.extend_from_slice(&7u64.to_be_bytes());
+            section.extend_from_slice(&sequence_no.to_be_bytes());
+            put_bytes(&mut section, &[]);
+        }
+        let mut snapshot = Snapshot::new();
+        snapshot.set_section("sessions", section);
+        assert!(matches!(
+            snapshot.install(&mut [&mut sessions]),
+            Err(Error::CorruptSnapshot("duplicate client session"))
+        ));
+        assert_eq!(sessions.apply(&entry(2, 7, 3), |_| vec![]), Some(vec![1]));
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+
+use crate::{Error, Result};
+
+const MAGIC: &[u8; 4] = b"RSNP";
+
+/// Format version written into every snapshot header.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A piece of node state that is saved into, and restored from, its own
+/// named snapshot section.
+pub trait SnapshotComponent {
+    /// Section name; must be unique among the registered components.
+    fn name(&self) -> &str;
+
+    fn save(&self) -> Vec<u8>;
+
+    /// Checks that `restore` would accept `data`, without changing the
+    /// component.
+    fn validate(&self, data: Option<&[u8]>) -> Result<()>;
+
+    /// Restores from the component's section, or from its defaults when the
+    /// snapshot has no such section. Only called once `validate` accepted
+    /// the same data, and leaves the component unchanged if it fails.
+    fn restore(&mut self, data: Option<&[u8]>) -> Result<()>;
+}
+
+/// Versioned container of named, length-prefixed sections.
+///
+/// Sections without a registered component are carried along untouched, so
+/// a snapshot written by a newer node survives being rewritten by an older
+/// one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    sections: BTreeMap<String, Vec<u8>>,
+}
+
+impl Snapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn section(&self, name: &str) -> Option<&[u8]> {
+        self.sections.get(name).map(Vec::as_slice)
+    }
+
+    pub fn set_section<S: Into<String>>(&mut self, name: S, data: Vec<u8>) {
+        self.sections.insert(name.into(), data);
+    }
+
+    pub fn section_names(&self) -> impl Iterator<Item = &str> {
+        self.sections.keys().map(String::as_str)
+    }
+
+    /// Saves every component into its section, leaving other sections alone.
+    pub fn capture(&mut self, components: &[&dyn SnapshotComponent]) {
+        for component in components {
+            self.set_section(component.name(), component.save());
+        }
+    }
+
+    /// Hands every component its section.
+    ///
+    /// Every section is validated before any component is restored, so a
+    /// snapshot with one bad section leaves all components untouched.
+    pub fn install(&self, components: &mut [&mut dyn SnapshotComponent]) -> Result<()> {
+        for component in components.iter() {
+            component.validate(self.section(component.name()))?;
+        }
+        for component in components.iter_mut() {
+            let data = self.section(component.name());
+            component.restore(data)?;
+        }
+        Ok(())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&SNAPSHOT_VERSION.to_be_bytes());
+        buf.extend_from_slice(&(self.sections.len() as u64).to_be_bytes());
+        for (name, data) in &self.sections {
+            put_bytes(&mut buf, name.as_bytes());
+            put_bytes(&mut buf, data);
+        }
+        buf
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::CorruptSnapshot("bad magic"));
+        }
+        let version = reader.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(Error::UnsupportedSnapshotVersion(version));
+        }
+
+        let mut snapshot = Snapshot::new();
+        for _ in 0..reader.u64()? {
+            let name = String::from_utf8(reader.bytes()?.to_vec())
+                .map_err(|_| Error::CorruptSnapshot("section name is not utf-8"))?;
+            let data = reader.bytes()?.to_vec();
+            if snapshot.sections.insert(name, data).is_some() {
+                return Err(Error::CorruptSnapshot("duplicate section"));
+            }
+        }
+        if !reader.is_empty() {
+            return Err(Error::CorruptSnapshot("trailing bytes"));
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Appends `data` prefixed with its length as a big-endian u64.
+pub(crate) fn put_bytes(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u64).to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < n {
+            return Err(Error::CorruptSnapshot("truncated"));
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Reads a slice written by `put_bytes`.
+    pub(crate) fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = usize::try_from(self.u64()?).map_err(|_| Error::CorruptSnapshot("truncated"))?;
+        self.take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Endpoint, Membership};
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Counter(u32);
+
+    impl SnapshotComponent for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn save(&self) -> Vec<u8> {
+            self.0.to_be_bytes().to_vec()
+        }
+
+        fn validate(&self, data: Option<&[u8]>) -> Result<()> {
+            match data {
+                Some(data) => Reader::new(data).u32().map(|_| ()),
+                None => Ok(()),
+            }
+        }
+
+        fn restore(&mut self, data: Option<&[u8]>) -> Result<()> {
+            self.0 = match data {
+                Some(data) => Reader::new(data).u32()?,
+                None => 0,
+            };
+            Ok(())
+        }
+    }
+
+    fn membership() -> Membership {
+        let mut membership = Membership::new(vec![
+            Endpoint::new("127.0.0.1", 8080).unwrap(),
+            Endpoint::new("127.0.0.1", 8081).unwrap(),
+        ])
+        .unwrap();
+        membership
+            .add_learner(1, Endpoint::new("::1", 8082).unwrap())
+            .unwrap();
+        membership
+            .add_observer(2, Endpoint::new("backup.local", 8083).unwrap())
+            .unwrap();
+        membership.commit_to(2);
+        membership
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let counter = Counter(42);
+        let membership = membership();
+        let mut snapshot = Snapshot::new();
+        snapshot.capture(&[&counter, &membership]);
+
+        let decoded = Snapshot::decode(&snapshot.encode()).unwrap();
+        assert_eq!(decoded, snapshot);
+
+        let mut restored_counter = Counter::default();
+        let mut restored_membership =
+            Membership::new(vec![Endpoint::new("127.0.0.1", 9000).unwrap()]).unwrap();
+        decoded
+            .install(&mut [&mut restored_counter, &mut restored_membership])
+            .unwrap();
+        assert_eq!(restored_counter, counter);
+        assert_eq!(restored_membership, membership);
+    }
+
+    #[test]
+    fn test_unknown_section_survives_rewrite() {
+        let mut snapshot = Snapshot::new();
+        snapshot.capture(&[&Counter(1)]);
+        snapshot.set_section("from_the_future", vec![1, 2, 3]);
+        let bytes = snapshot.encode();
+
+        let mut rewritten = Snapshot::decode(&bytes).unwrap();
+        let mut counter = Counter::default();
+        rewritten.install(&mut [&mut counter]).unwrap();
+        counter.0 += 1;
+        rewritten.capture(&[&counter]);
+
+        let decoded = Snapshot::decode(&rewritten.encode()).unwrap();
+        assert_eq!(decoded.section("from_the_future"), Some(&[1, 2, 3][..]));
+        assert_eq!(decoded.section("counter"), Some(&2u32.to_be_bytes()[..]));
+    }
+
+    #[test]
+    fn test_missing_section_uses_default() {
+        let mut counter = Counter(7);
+        Snapshot::new().install(&mut [&mut counter]).unwrap();
+        assert_eq!(counter, Counter(0));
+
+        let mut membership =
+            Membership::new(vec![Endpoint::new("127.0.0.1", 9000).unwrap()]).unwrap();
+        assert!(Snapshot::new().install(&mut [&mut membership]).is_err());
+    }
+
+    #[test]
+    fn test_failed_install_changes_nothing() {
+        let mut snapshot = Snapshot::new();
+        snapshot.capture(&[&Counter(9), &membership()]);
+        snapshot.set_section("membership", vec![0, 0, 0]);
+
+        let mut counter = Counter(1);
+        let mut restored = membership();
+        restored
+            .remove_server(3, &Endpoint::new("127.0.0.1", 8081).unwrap())
+            .unwrap();
+        let before = restored.clone();
+        assert!(snapshot
+            .install(&mut [&mut counter, &mut restored])
+            .is_err());
+        assert_eq!(counter, Counter(1));
+        assert_eq!(restored, before);
+    }
+
+    #[test]
+    fn test_decode_rejects() {
+        let bytes = Snapshot::new().encode();
+        assert!(matches!(
+            Snapshot::decode(b"nope"),
+            Err(Error::CorruptSnapshot("bad magic"))
+        ));
+        assert!(matches!(
+            Snapshot::decode(&bytes[..bytes.len() - 1]),
+            Err(Error::CorruptSnapshot("truncated"))
+        ));
+
+        let mut newer = bytes.clone();
+        newer[4..8].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_be_bytes());
+        assert!(matches!(
+            Snapshot::decode(&newer),
+            Err(Error::UnsupportedSnapshotVersion(v)) if v == SNAPSHOT_VERSION + 1
+        ));
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(matches!(
+            Snapshot::decode(&trailing),
+            Err(Error::CorruptSnapshot("trailing bytes"))
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{Endpoint, Result};
+
+const HARD_STATE_FILE: &str = "hard_state";
+const HARD_STATE_TMP_FILE: &str = "hard_state.tmp";
+
+/// State that must survive a restart before a node answers any RPC.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HardState {
+    pub term: usize,
+    pub voted_for: Option<Endpoint>,
+}
+
+impl HardState {
+    fn encode(&self) -> String {
+        let voted_for = self
+            .voted_for
+            .as_ref()
+            .map(Endpoint::to_string)
+            .unwrap_or_default();
+        format!("{}\n{}\n", self.term, voted_for)
+    }
+
+    fn decode(s: &str) -> io::Result<Self> {
+        let invalid = |reason| io::Error::new(io::ErrorKind::InvalidData, reason);
+        let mut lines = s.split('\n');
+        let term = lines
+            .next()
+            .and_then(|term| term.parse().ok())
+            .ok_or_else(|| invalid("invalid term in hard state"))?;
+        let voted_for = match lines.next() {
+            Some("") => None,
+            Some(endpoint) => Some(
+                endpoint
+                    .parse()
+                    .map_err(|_| invalid("invalid voted_for in hard state"))?,
+            ),
+            None => return Err(invalid("truncated hard state")),
+        };
+        if lines.next() != Some("") || lines.next().is_some() {
+            return Err(invalid("trailing data in hard state"));
+        }
+        Ok(Self { term, voted_for })
+    }
+}
+
+/// Persists `state` in `dir`, replacing the previous one atomically.
+///
+/// The new state is written and synced to a temporary file, which is then
+/// renamed over the old one. A crash at any point leaves either the old or
+/// the new state on disk, never a mix of the two.
+pub fn save_hard_state(dir: &Path, state: &HardState) -> Result<()> {
+    let tmp = write_tmp(dir, state)?;
+    fs::rename(tmp, dir.join(HARD_STATE_FILE))?;
+    sync_dir(dir)?;
+    Ok(())
+}
+
+/// Loads the state saved in `dir`, or the default when none was saved yet.
+/// A leftover temporary file from an interrupted save is ignored.
+pub fn load_hard_state(dir: &Path) -> Result<HardState> {
+    match fs::read_to_string(dir.join(HARD_STATE_FILE)) {
+        Ok(s) => Ok(HardState::decode(&s)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(HardState::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write_tmp(dir: &Path, state: &HardState) -> io::Result<PathBuf> {
+    let path = dir.join(HARD_STATE_TMP_FILE);
+    let mut file = File::create(&path)?;
+    file.write_all(state.encode().as_bytes())?;
+    file.sync_all()?;
+    Ok(path)
+}
+
+/// Makes a rename in `dir` durable.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+    use crate::Error;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("raft-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn state(term: usize, voted_for: Option<&str>) -> HardState {
+        HardState {
+            term,
+            voted_for: voted_for.map(|endpoint| endpoint.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = TempDir::new("hard-state-round-trip");
+        assert_eq!(load_hard_state(&dir.0).unwrap(), HardState::default());
+
+        for state in [
+            state(1, Some("127.0.0.1:8080")),
+            state(2, None),
+            state(3, Some("[::1]:8081")),
+        ]
+        .iter()
+        {
+            save_hard_state(&dir.0, state).unwrap();
+            assert_eq!(load_hard_state(&dir.0).unwrap(), *state);
+        }
+    }
+
+    #[test]
+    fn test_crash_before_rename_keeps_old_state() {
+        let dir = TempDir::new("hard-state-crash");
+        let old = state(4, Some("127.0.0.1:8080"));
+        save_hard_state(&dir.0, &old).unwrap();
+
+        // Crash after the new state is written but before it is renamed.
+        write_tmp(&dir.0, &state(5, Some("127.0.0.1:8081"))).unwrap();
+        assert_eq!(load_hard_state(&dir.0).unwrap(), old);
+
+        // Crash halfway through writing the temporary file.
+        fs::write(dir.0.join(HARD_STATE_TMP_FILE), b"6\n127.0").unwrap();
+        assert_eq!(load_hard_state(&dir.0).unwrap(), old);
+
+        let new = state(7, None);
+        save_hard_state(&dir.0, &new).unwrap();
+        assert_eq!(load_hard_state(&dir.0).unwrap(), new);
+    }
+
+    #[test]
+    fn test_corrupt_state_is_an_error() {
+        let dir = TempDir::new("hard-state-corrupt");
+        for corrupt in ["", "x\n\n", "1\n", "1\nnope\n", "1\n\nextra"].iter() {
+            fs::write(dir.0.join(HARD_STATE_FILE), corrupt).unwrap();
+            assert!(
+                matches!(load_hard_state(&dir.0), Err(Error::Storage(_))),
+                "{:?}",
+                corrupt
+            );
+        }
+    }
+}